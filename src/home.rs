@@ -46,12 +46,12 @@ impl Application {
         }
     }
 
-    pub fn view(&self) -> Element<Event> {
+    pub fn view(&self) -> Element<'_, Event> {
         home(self)
     }
 }
 
-fn home<'a>(app: &Application) -> Element<Event> {
+fn home(app: &Application) -> Element<'_, Event> {
     let name = "Bob";
 
     let sidebar = container(
@@ -120,13 +120,12 @@ fn home<'a>(app: &Application) -> Element<Event> {
             horizontal_rule(0.5),
             container(test_input).padding(10)
         ]
-        .into()
     };
 
     column![row![sidebar, vertical_rule(0.5), main_content]].into()
 }
 
-fn message_buble(message: &String) -> Element<Event> {
+fn message_buble(message: &String) -> Element<'_, Event> {
     let bubble = container(column![
         text("Me")
             .font(Font {
@@ -0,0 +1,11 @@
+pub mod links;
+pub mod markdown;
+pub mod model;
+pub mod passphrase;
+#[cfg(feature = "qr")]
+pub mod qr;
+pub mod status;
+#[cfg(feature = "native-json-length")]
+pub mod text;
+#[cfg(feature = "image")]
+pub mod thumbnail;
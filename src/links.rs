@@ -0,0 +1,498 @@
+//! Parsing and re-serialization of SimpleX connection links.
+//!
+//! Supports full links (`simplex:/invitation#/?v=…&smp=…`,
+//! `https://simplex.chat/contact#/?…`) and short links
+//! (`https://smp.example.com/a#<key>`, `simplex:/a#<key>?h=<host>`).
+//!
+//! Short link data is base64url without padding. Contact, channel and
+//! group links (`a`, `c`, `g`) carry a 32-byte key; invitation links (`i`)
+//! carry the 24-byte link id followed by the key.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::markdown::SimplexLinkType;
+
+const LINK_KEY_BYTES: usize = 32;
+const LINK_ID_BYTES: usize = 24;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SimplexLink {
+    pub scheme: LinkScheme,
+    pub link_type: LinkType,
+    pub data: LinkData,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LinkType {
+    Invitation,
+    Contact,
+    Channel,
+    Group,
+}
+
+impl From<LinkType> for SimplexLinkType {
+    fn from(link_type: LinkType) -> Self {
+        match link_type {
+            LinkType::Invitation => Self::Invitation,
+            LinkType::Contact => Self::Contact,
+            LinkType::Channel => Self::Channel,
+            LinkType::Group => Self::Group,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LinkScheme {
+    Simplex,
+    Https { host: String },
+}
+
+/// The query is kept exactly as it appeared in the link, so re-serializing a
+/// parsed link gives back the original string. `key` is the base64url link
+/// data, including the link id for invitations.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LinkData {
+    Full { query: String },
+    Short { key: String, query: Option<String> },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LinkError {
+    UnknownScheme,
+    UnknownType(String),
+    MissingParameter(&'static str),
+    InvalidKey,
+    InvalidEncoding,
+}
+
+impl fmt::Display for LinkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownScheme => write!(f, "not a simplex: or https: link"),
+            Self::UnknownType(path) => write!(f, "unknown link type: {path}"),
+            Self::MissingParameter(name) => write!(f, "missing link parameter: {name}"),
+            Self::InvalidKey => {
+                write!(f, "short link data is not base64url of the expected length")
+            }
+            Self::InvalidEncoding => write!(f, "invalid percent-encoding in link"),
+        }
+    }
+}
+
+impl std::error::Error for LinkError {}
+
+impl SimplexLink {
+    pub fn is_short(&self) -> bool {
+        matches!(self.data, LinkData::Short { .. })
+    }
+
+    /// Returns the decoded value of a query parameter.
+    pub fn param(&self, name: &str) -> Option<String> {
+        let query = match &self.data {
+            LinkData::Full { query } => query,
+            LinkData::Short { query, .. } => query.as_ref()?,
+        };
+        query_pairs(query)
+            .find(|(key, _)| *key == name)
+            .and_then(|(_, value)| percent_decode(value).ok())
+    }
+
+    /// Hosts of the SMP server the link points at.
+    pub fn smp_hosts(&self) -> Vec<String> {
+        match (&self.data, &self.scheme) {
+            (LinkData::Full { .. }, _) => self
+                .param("smp")
+                .map(|smp| queue_hosts(&smp))
+                .unwrap_or_default(),
+            (LinkData::Short { .. }, LinkScheme::Https { host }) => vec![host.clone()],
+            (LinkData::Short { .. }, LinkScheme::Simplex) => self
+                .param("h")
+                .map(|hosts| hosts.split(',').map(String::from).collect())
+                .unwrap_or_default(),
+        }
+    }
+
+    /// The `groupLinkId` carried in the `data` parameter of full group links.
+    pub fn group_link_id(&self) -> Option<String> {
+        let data: serde_json::Value = serde_json::from_str(&self.param("data")?).ok()?;
+        data.get("groupLinkId")?.as_str().map(String::from)
+    }
+
+    /// Returns the same link using the `simplex:/` scheme. Short links carry
+    /// their server host over as the `h` parameter.
+    pub fn to_simplex_scheme(&self) -> Self {
+        let mut link = self.clone();
+        if let (LinkScheme::Https { host }, LinkData::Short { query, .. }) =
+            (&self.scheme, &mut link.data)
+        {
+            let has_host = query
+                .as_deref()
+                .is_some_and(|query| query_pairs(query).any(|(key, _)| key == "h"));
+            if !has_host {
+                *query = Some(match query.as_deref() {
+                    None | Some("") => format!("h={host}"),
+                    Some(query) => format!("{query}&h={host}"),
+                });
+            }
+        }
+        link.scheme = LinkScheme::Simplex;
+        link
+    }
+}
+
+impl FromStr for SimplexLink {
+    type Err = LinkError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (scheme, rest) = if let Some(rest) = s.strip_prefix("simplex:/") {
+            (LinkScheme::Simplex, rest)
+        } else if let Some(rest) = s.strip_prefix("https://") {
+            let (host, rest) = rest.split_once('/').ok_or(LinkError::UnknownScheme)?;
+            let host = host.to_string();
+            (LinkScheme::Https { host }, rest)
+        } else {
+            return Err(LinkError::UnknownScheme);
+        };
+
+        let (path, fragment) = rest.split_once('#').unwrap_or((rest, ""));
+        match path {
+            "invitation" | "contact" => {
+                let query = fragment
+                    .strip_prefix("/?")
+                    .ok_or(LinkError::MissingParameter("smp"))?;
+                validate_query(query)?;
+                let mut link = SimplexLink {
+                    scheme,
+                    link_type: LinkType::Invitation,
+                    data: LinkData::Full {
+                        query: query.to_string(),
+                    },
+                };
+                if link.param("smp").is_none() {
+                    return Err(LinkError::MissingParameter("smp"));
+                }
+                if path == "contact" {
+                    link.link_type = if link.group_link_id().is_some() {
+                        LinkType::Group
+                    } else {
+                        LinkType::Contact
+                    };
+                }
+                Ok(link)
+            }
+            "i" | "a" | "c" | "g" => {
+                let (link_type, data_bytes) = match path {
+                    "i" => (LinkType::Invitation, LINK_ID_BYTES + LINK_KEY_BYTES),
+                    "a" => (LinkType::Contact, LINK_KEY_BYTES),
+                    "c" => (LinkType::Channel, LINK_KEY_BYTES),
+                    _ => (LinkType::Group, LINK_KEY_BYTES),
+                };
+                let (key, query) = match fragment.split_once('?') {
+                    Some((key, query)) => (key, Some(query)),
+                    None => (fragment, None),
+                };
+                if !is_base64url(key, data_bytes) {
+                    return Err(LinkError::InvalidKey);
+                }
+                if let Some(query) = query {
+                    validate_query(query)?;
+                }
+                let link = SimplexLink {
+                    scheme,
+                    link_type,
+                    data: LinkData::Short {
+                        key: key.to_string(),
+                        query: query.map(String::from),
+                    },
+                };
+                if link.scheme == LinkScheme::Simplex && link.param("h").is_none() {
+                    return Err(LinkError::MissingParameter("h"));
+                }
+                Ok(link)
+            }
+            _ => Err(LinkError::UnknownType(path.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for SimplexLink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.scheme {
+            LinkScheme::Simplex => write!(f, "simplex:/")?,
+            LinkScheme::Https { host } => write!(f, "https://{host}/")?,
+        }
+        match &self.data {
+            LinkData::Full { query } => {
+                let path = match self.link_type {
+                    LinkType::Invitation => "invitation",
+                    LinkType::Contact | LinkType::Channel | LinkType::Group => "contact",
+                };
+                write!(f, "{path}#/?{query}")
+            }
+            LinkData::Short { key, query } => {
+                let path = match self.link_type {
+                    LinkType::Invitation => "i",
+                    LinkType::Contact => "a",
+                    LinkType::Channel => "c",
+                    LinkType::Group => "g",
+                };
+                write!(f, "{path}#{key}")?;
+                if let Some(query) = query {
+                    write!(f, "?{query}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Whether `s` is exactly `len` bytes, base64url-encoded without padding.
+/// Bits of the last character past the end of the data must be zero.
+fn is_base64url(s: &str, len: usize) -> bool {
+    let value = |c: u8| match c {
+        b'A'..=b'Z' => Some(c - b'A'),
+        b'a'..=b'z' => Some(c - b'a' + 26),
+        b'0'..=b'9' => Some(c - b'0' + 52),
+        b'-' => Some(62),
+        b'_' => Some(63),
+        _ => None,
+    };
+    let chars = (len * 8).div_ceil(6);
+    let spare_bits = chars * 6 - len * 8;
+    let bytes = s.as_bytes();
+    bytes.len() == chars
+        && bytes.iter().all(|&c| value(c).is_some())
+        && bytes
+            .last()
+            .and_then(|&last| value(last))
+            .is_some_and(|last| last & ((1 << spare_bits) - 1) == 0)
+}
+
+fn query_pairs(query: &str) -> impl Iterator<Item = (&str, &str)> {
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| pair.split_once('=').unwrap_or((pair, "")))
+}
+
+fn validate_query(query: &str) -> Result<(), LinkError> {
+    query_pairs(query).try_for_each(|(_, value)| percent_decode(value).map(|_| ()))
+}
+
+/// Hosts of an SMP queue URI: `smp://<key hash>@<host>[,<host>…][:<port>]/…`.
+fn queue_hosts(uri: &str) -> Vec<String> {
+    let Some((_, server)) = uri.split_once('@') else {
+        return Vec::new();
+    };
+    let server = server.split(['/', '#']).next().unwrap_or_default();
+    let hosts = server.rsplit_once(':').map_or(server, |(hosts, _)| hosts);
+    hosts
+        .split(',')
+        .filter(|host| !host.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+fn percent_decode(s: &str) -> Result<String, LinkError> {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = s
+                .get(i + 1..i + 3)
+                .filter(|hex| hex.bytes().all(|c| c.is_ascii_hexdigit()))
+                .ok_or(LinkError::InvalidEncoding)?;
+            let byte = u8::from_str_radix(hex, 16).map_err(|_| LinkError::InvalidEncoding)?;
+            out.push(byte);
+            i += 3;
+        } else {
+            out.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(out).map_err(|_| LinkError::InvalidEncoding)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: &str = "lJxq0nB3Zl6pX4kVbT2mQ8rYwE5uA1cD9fG7hJ3kL6M";
+
+    /// Link id and key of an invitation short link, 56 bytes in all.
+    const INVITATION_DATA: &str =
+        "Rfh6mnxhBBxJC3TmZsbKbYW3HaXJ9J8vxyI-ExzgStWhvxcz5ZLa8NzMfSc5mws336tyR1mlDb4";
+
+    const SHORT_INVITATION: &str = "simplex:/i#Rfh6mnxhBBxJC3TmZsbKbYW3HaXJ9J8vxyI-ExzgStWhvxcz5ZLa8NzMfSc5mws336tyR1mlDb4?h=smp4.simplex.im,o5vmywmrnaxalvz6wi3zicyftgio6psuvyniis6gco6bp6ekl4cqj4id.onion&c=6gZUvi6be9Bt-PKXkteUdm9FCK2MUtcK0SlVUKs7O7w";
+
+    const INVITATION: &str = "simplex:/invitation#/?v=2-7&smp=smp%3A%2F%2Fu2dS9sG8nMNURyZwqASV4yROM28Er0luVTx5X1CsMrU%3D%40smp4.simplex.im%2FabcQueue%23%2F%3Fv%3D1-3%26dh%3DMCowBQYDK2VuAyEA&e2e=v%3D2-3%26x3dh%3DMEIwBQYDK2VvAzkA";
+
+    const CONTACT: &str = "https://simplex.chat/contact#/?v=2-7&smp=smp%3A%2F%2Fhash%40smp8.simplex.im%2Cbeccx4yfxxbvyhqypaavemqurytl6hozr47wfc7uuecacjqdvwpw2xid.onion%3A5223%2Fq%23%2F%3Fv%3D1-3";
+
+    const GROUP: &str = "https://simplex.chat/contact#/?v=2-7&smp=smp%3A%2F%2Fhash%40smp8.simplex.im%2Fq&data=%7B%22groupLinkId%22%3A%22abc%3D%3D%22%7D";
+
+    fn parse(s: &str) -> SimplexLink {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn parses_full_invitation() {
+        let link = parse(INVITATION);
+        assert_eq!(link.scheme, LinkScheme::Simplex);
+        assert_eq!(link.link_type, LinkType::Invitation);
+        assert!(!link.is_short());
+        assert_eq!(link.smp_hosts(), ["smp4.simplex.im"]);
+        assert_eq!(link.group_link_id(), None);
+        assert_eq!(link.param("v").as_deref(), Some("2-7"));
+    }
+
+    #[test]
+    fn parses_full_contact() {
+        let link = parse(CONTACT);
+        assert_eq!(
+            link.scheme,
+            LinkScheme::Https {
+                host: "simplex.chat".to_string()
+            }
+        );
+        assert_eq!(link.link_type, LinkType::Contact);
+        assert_eq!(
+            link.smp_hosts(),
+            [
+                "smp8.simplex.im",
+                "beccx4yfxxbvyhqypaavemqurytl6hozr47wfc7uuecacjqdvwpw2xid.onion"
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_full_group() {
+        let link = parse(GROUP);
+        assert_eq!(link.link_type, LinkType::Group);
+        assert_eq!(link.group_link_id().as_deref(), Some("abc=="));
+    }
+
+    #[test]
+    fn parses_short_links() {
+        let https = parse(&format!("https://smp4.simplex.im/a#{KEY}"));
+        assert!(https.is_short());
+        assert_eq!(https.link_type, LinkType::Contact);
+        assert_eq!(https.smp_hosts(), ["smp4.simplex.im"]);
+
+        let simplex = parse(&format!(
+            "simplex:/g#{KEY}?h=smp1.example.com,smp2.example.com&c=xyz"
+        ));
+        assert_eq!(simplex.link_type, LinkType::Group);
+        assert_eq!(
+            simplex.smp_hosts(),
+            ["smp1.example.com", "smp2.example.com"]
+        );
+
+        let channel = parse(&format!("https://smp4.simplex.im/c#{KEY}"));
+        assert_eq!(channel.link_type, LinkType::Channel);
+    }
+
+    #[test]
+    fn parses_short_invitation() {
+        let link = parse(SHORT_INVITATION);
+        assert_eq!(link.link_type, LinkType::Invitation);
+        assert_eq!(
+            link.data,
+            LinkData::Short {
+                key: INVITATION_DATA.to_string(),
+                query: Some(SHORT_INVITATION.split_once('?').unwrap().1.to_string()),
+            }
+        );
+        assert_eq!(
+            link.smp_hosts(),
+            [
+                "smp4.simplex.im",
+                "o5vmywmrnaxalvz6wi3zicyftgio6psuvyniis6gco6bp6ekl4cqj4id.onion"
+            ]
+        );
+    }
+
+    #[test]
+    fn converts_short_link_to_simplex_scheme() {
+        let link = parse(&format!("https://smp4.simplex.im/a#{KEY}"));
+        assert_eq!(
+            link.to_simplex_scheme().to_string(),
+            format!("simplex:/a#{KEY}?h=smp4.simplex.im")
+        );
+    }
+
+    #[test]
+    fn round_trips() {
+        for s in [
+            INVITATION.to_string(),
+            CONTACT.to_string(),
+            GROUP.to_string(),
+            SHORT_INVITATION.to_string(),
+            format!("https://smp4.simplex.im/i#{INVITATION_DATA}"),
+            format!("https://smp4.simplex.im/i#{INVITATION_DATA}?"),
+            format!("https://smp4.simplex.im/i#{INVITATION_DATA}?a&&b"),
+            format!("https://smp4.simplex.im/c#{KEY}"),
+            format!("simplex:/a#{KEY}?h=smp.example.com&"),
+        ] {
+            assert_eq!(parse(&s).to_string(), s);
+        }
+    }
+
+    #[test]
+    fn rejects_non_simplex_urls() {
+        for s in [
+            "https://github.com/a#readme",
+            "https://example.com/foo",
+            "https://example.com",
+            "http://simplex.chat/contact#/?v=1&smp=x",
+            "simplex:/contact#/?v=1",
+            "simplex:/i#",
+        ] {
+            assert!(s.parse::<SimplexLink>().is_err(), "{s}");
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_short_keys() {
+        let short = &KEY[..42];
+        let padded = format!("{KEY}=");
+        let bad_tail = format!("{}N", &KEY[..42]);
+        for key in [short, padded.as_str(), bad_tail.as_str()] {
+            let link = format!("https://smp4.simplex.im/a#{key}");
+            assert_eq!(link.parse::<SimplexLink>(), Err(LinkError::InvalidKey));
+        }
+    }
+
+    #[test]
+    fn requires_host_for_simplex_short_links() {
+        let link = format!("simplex:/a#{KEY}");
+        assert_eq!(
+            link.parse::<SimplexLink>(),
+            Err(LinkError::MissingParameter("h"))
+        );
+    }
+
+    #[test]
+    fn short_link_data_length_depends_on_type() {
+        for link in [
+            format!("https://smp4.simplex.im/i#{KEY}"),
+            format!("https://smp4.simplex.im/a#{INVITATION_DATA}"),
+            format!("https://smp4.simplex.im/g#{INVITATION_DATA}"),
+        ] {
+            assert_eq!(link.parse::<SimplexLink>(), Err(LinkError::InvalidKey));
+        }
+    }
+
+    #[test]
+    fn rejects_signed_percent_escapes() {
+        for s in [
+            "simplex:/invitation#/?smp=%+1",
+            "simplex:/invitation#/?smp=%-1",
+            "simplex:/invitation#/?smp=%1",
+        ] {
+            assert_eq!(s.parse::<SimplexLink>(), Err(LinkError::InvalidEncoding));
+        }
+    }
+}
@@ -0,0 +1,223 @@
+//! Typed form of the JSON produced by chatcore's `chat_parse_markdown`.
+//!
+//! Formats, colors and link types added to chatcore later are kept as sent,
+//! so they serialize back unchanged.
+
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "native-markdown")]
+mod native;
+pub mod render;
+
+#[cfg(feature = "native-markdown")]
+pub use native::parse_markdown;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ParsedMarkdown {
+    /// `None` when the text has no formatting at all.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub formatted_text: Option<Vec<FormattedText>>,
+}
+
+impl FromStr for ParsedMarkdown {
+    type Err = serde_json::Error;
+
+    fn from_str(json: &str) -> Result<Self, Self::Err> {
+        serde_json::from_str(json)
+    }
+}
+
+impl ParsedMarkdown {
+    /// Returns the formatted chunks, falling back to a single plain chunk of
+    /// `source` when chatcore found nothing to format.
+    pub fn into_chunks(self, source: &str) -> Vec<FormattedText> {
+        self.formatted_text
+            .unwrap_or_else(|| vec![FormattedText::plain(source)])
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FormattedText {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<Format>,
+    pub text: String,
+}
+
+impl FormattedText {
+    pub fn plain(text: impl Into<String>) -> Self {
+        Self {
+            format: None,
+            text: text.into(),
+        }
+    }
+
+    pub fn new(format: Format, text: impl Into<String>) -> Self {
+        Self {
+            format: Some(format),
+            text: text.into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum Format {
+    Bold,
+    Italic,
+    StrikeThrough,
+    Snippet,
+    Secret,
+    Colored {
+        color: Color,
+    },
+    Uri,
+    #[serde(rename_all = "camelCase")]
+    SimplexLink {
+        link_type: SimplexLinkType,
+        simplex_uri: String,
+        smp_hosts: Vec<String>,
+    },
+    #[serde(rename_all = "camelCase")]
+    Mention {
+        member_name: String,
+    },
+    Email,
+    Phone,
+    /// A format added to chatcore after this crate was written.
+    #[serde(untagged)]
+    Unknown(serde_json::Value),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Color {
+    Red,
+    Green,
+    Blue,
+    Yellow,
+    Cyan,
+    Magenta,
+    Black,
+    White,
+    #[serde(untagged)]
+    Unknown(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SimplexLinkType {
+    Contact,
+    Invitation,
+    Group,
+    Channel,
+    #[serde(untagged)]
+    Unknown(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_chatcore_output() {
+        let json = r#"{"formattedText":[
+            {"text":"bold","format":{"type":"bold"}},{"text":" "},
+            {"text":"italic","format":{"type":"italic"}},{"text":" "},
+            {"text":"strike","format":{"type":"strikeThrough"}},{"text":" "},
+            {"text":"code","format":{"type":"snippet"}},{"text":" "},
+            {"text":"secret","format":{"type":"secret"}},{"text":" "},
+            {"text":"red","format":{"type":"colored","color":"red"}},{"text":" "},
+            {"text":"https://example.com","format":{"type":"uri"}},{"text":" "},
+            {"text":"simplex:/contact#/?v=2-7&smp=x","format":{"type":"simplexLink","linkType":"contact","simplexUri":"simplex:/contact#/?v=2-7&smp=x","smpHosts":["smp.example.com"]}},{"text":" "},
+            {"text":"@alice","format":{"type":"mention","memberName":"alice"}},{"text":" "},
+            {"text":"a@example.com","format":{"type":"email"}},{"text":" "},
+            {"text":"+1 555 123 4567","format":{"type":"phone"}}
+        ]}"#;
+        let chunks = json
+            .parse::<ParsedMarkdown>()
+            .unwrap()
+            .formatted_text
+            .unwrap();
+        let formats: Vec<Format> = chunks
+            .into_iter()
+            .filter_map(|chunk| chunk.format)
+            .collect();
+        assert_eq!(
+            formats,
+            [
+                Format::Bold,
+                Format::Italic,
+                Format::StrikeThrough,
+                Format::Snippet,
+                Format::Secret,
+                Format::Colored { color: Color::Red },
+                Format::Uri,
+                Format::SimplexLink {
+                    link_type: SimplexLinkType::Contact,
+                    simplex_uri: "simplex:/contact#/?v=2-7&smp=x".to_string(),
+                    smp_hosts: vec!["smp.example.com".to_string()],
+                },
+                Format::Mention {
+                    member_name: "alice".to_string()
+                },
+                Format::Email,
+                Format::Phone,
+            ]
+        );
+    }
+
+    #[test]
+    fn plain_text_has_no_chunks() {
+        let parsed: ParsedMarkdown = "{}".parse().unwrap();
+        assert_eq!(parsed.into_chunks("hi"), [FormattedText::plain("hi")]);
+    }
+
+    #[test]
+    fn unknown_values_degrade() {
+        let json = r#"{"formattedText":[
+            {"text":"a","format":{"type":"colored","color":"orange"}},
+            {"text":"b","format":{"type":"simplexLink","linkType":"relay","simplexUri":"simplex:/r#k","smpHosts":[]}},
+            {"text":"c","format":{"type":"spoiler","level":2}}
+        ]}"#;
+        let formats: Vec<Option<Format>> = json
+            .parse::<ParsedMarkdown>()
+            .unwrap()
+            .formatted_text
+            .unwrap()
+            .into_iter()
+            .map(|chunk| chunk.format)
+            .collect();
+        assert_eq!(
+            formats,
+            [
+                Some(Format::Colored {
+                    color: Color::Unknown("orange".into())
+                }),
+                Some(Format::SimplexLink {
+                    link_type: SimplexLinkType::Unknown("relay".into()),
+                    simplex_uri: "simplex:/r#k".to_string(),
+                    smp_hosts: vec![],
+                }),
+                Some(Format::Unknown(
+                    serde_json::json!({"type": "spoiler", "level": 2})
+                )),
+            ]
+        );
+
+        let round_trip: Vec<serde_json::Value> = formats
+            .iter()
+            .map(|format| serde_json::to_value(format).unwrap())
+            .collect();
+        assert_eq!(
+            round_trip,
+            [
+                serde_json::json!({"type": "colored", "color": "orange"}),
+                serde_json::json!({"type": "simplexLink", "linkType": "relay", "simplexUri": "simplex:/r#k", "smpHosts": []}),
+                serde_json::json!({"type": "spoiler", "level": 2}),
+            ]
+        );
+    }
+}
@@ -0,0 +1,497 @@
+//! Pure-Rust port of chatcore's message markdown grammar
+//! (`Simplex.Chat.Markdown`), for contexts where the Haskell runtime is not
+//! available.
+
+use unicode_properties::{GeneralCategoryGroup, UnicodeGeneralCategory};
+
+use super::{Color, Format, FormattedText, ParsedMarkdown};
+use crate::links::SimplexLink;
+
+type Fragment = (Vec<FormattedText>, usize);
+
+/// Parses `s` following the grammar of `chat_parse_markdown`.
+///
+/// One difference remains: chatcore validates email addresses with the
+/// `email-validate` package, while this parser applies a simplified version
+/// of the same rules, so unusual addresses (quoted local parts, IP literal
+/// domains) may be classified differently.
+pub fn parse_markdown(s: &str) -> ParsedMarkdown {
+    let mut chunks = Vec::new();
+    // Like Haskell's `T.lines`, a trailing newline doesn't start a new line.
+    for (i, line) in s.split_terminator('\n').enumerate() {
+        if i > 0 {
+            chunks.push(FormattedText::plain("\n"));
+        }
+        parse_line(line, &mut chunks);
+    }
+
+    if chunks.iter().all(|chunk| chunk.format.is_none()) {
+        return ParsedMarkdown::default();
+    }
+
+    let mut merged: Vec<FormattedText> = Vec::with_capacity(chunks.len());
+    for chunk in chunks {
+        match merged.last_mut() {
+            Some(last) if last.format == chunk.format => last.text.push_str(&chunk.text),
+            _ => merged.push(chunk),
+        }
+    }
+
+    ParsedMarkdown {
+        formatted_text: Some(merged),
+    }
+}
+
+fn parse_line(line: &str, out: &mut Vec<FormattedText>) {
+    let mut rest = line;
+    while let Some(c) = rest.chars().next() {
+        let (fragment, consumed) = match c {
+            ' ' => {
+                let len = rest.len() - rest.trim_start_matches(' ').len();
+                (vec![FormattedText::plain(&rest[..len])], len)
+            }
+            '*' => formatted(rest, '*', Format::Bold),
+            '_' => formatted(rest, '_', Format::Italic),
+            '~' => formatted(rest, '~', Format::StrikeThrough),
+            '`' => formatted(rest, '`', Format::Snippet),
+            '#' => secret(rest),
+            '!' => colored(rest).unwrap_or_else(|| word(rest)),
+            '@' => mention(rest).unwrap_or_else(|| word(rest)),
+            c if c.is_ascii_digit() || c == '+' => phone(rest).unwrap_or_else(|| word(rest)),
+            _ => word(rest),
+        };
+        out.extend(fragment);
+        rest = &rest[consumed..];
+    }
+}
+
+fn formatted(s: &str, marker: char, format: Format) -> Fragment {
+    let body = &s[1..];
+    let Some(end) = body.find(marker) else {
+        return (vec![FormattedText::plain(s)], s.len());
+    };
+    let text = &body[..end];
+    let consumed = end + 2;
+    if text.is_empty() || text.starts_with(' ') || text.ends_with(' ') {
+        (vec![FormattedText::plain(&s[..consumed])], consumed)
+    } else {
+        (vec![FormattedText::new(format, text)], consumed)
+    }
+}
+
+fn secret(s: &str) -> Fragment {
+    let body = &s[1..];
+    let before = body.len() - body.trim_start_matches('#').len();
+    let text_len = body[before..].find('#').unwrap_or(body.len() - before);
+    let text = &body[before..before + text_len];
+    let tail = &body[before + text_len..];
+    let after = tail.len() - tail.trim_start_matches('#').len();
+    let whole = &body[..before + text_len + after];
+    let consumed = whole.len() + 1;
+
+    if after == 0 || text.is_empty() || text.starts_with(' ') || text.ends_with(' ') {
+        (vec![FormattedText::plain(&s[..consumed])], consumed)
+    } else {
+        let secret = &whole[..whole.len() - 1];
+        (vec![FormattedText::new(Format::Secret, secret)], consumed)
+    }
+}
+
+fn colored(s: &str) -> Option<Fragment> {
+    let (color, color_len) = color(&s[1..])?;
+    let rest = &s[1 + color_len..];
+    let space = rest.chars().next().filter(|c| c.is_whitespace())?;
+    let body = &rest[space.len_utf8()..];
+    if body.starts_with(['!', ' ']) {
+        return None;
+    }
+    let end = body.find('!')?;
+    let text = &body[..end];
+    if text.ends_with(' ') {
+        return None;
+    }
+    let consumed = s.len() - body.len() + end + 1;
+    Some((
+        vec![FormattedText::new(Format::Colored { color }, text)],
+        consumed,
+    ))
+}
+
+fn color(s: &str) -> Option<(Color, usize)> {
+    let (color, name) = match s.chars().next()? {
+        'r' => (Color::Red, "red"),
+        'g' => (Color::Green, "green"),
+        'b' => (Color::Blue, "blue"),
+        'y' => (Color::Yellow, "yellow"),
+        'c' => (Color::Cyan, "cyan"),
+        'm' => (Color::Magenta, "magenta"),
+        '1' => return Some((Color::Red, 1)),
+        '2' => return Some((Color::Green, 1)),
+        '3' => return Some((Color::Blue, 1)),
+        '4' => return Some((Color::Yellow, 1)),
+        '5' => return Some((Color::Cyan, 1)),
+        '6' => return Some((Color::Magenta, 1)),
+        _ => return None,
+    };
+    let len = if s.starts_with(name) { name.len() } else { 1 };
+    Some((color, len))
+}
+
+fn mention(s: &str) -> Option<Fragment> {
+    let body = &s[1..];
+    let first = body.chars().next()?;
+
+    if first == '\'' {
+        let quoted = &body[1..];
+        if !quoted.starts_with(is_name_char) {
+            return None;
+        }
+        let end = quoted.find('\'')?;
+        let name = &quoted[..end];
+        let consumed = end + 3;
+        let mention = Format::Mention {
+            member_name: name.to_string(),
+        };
+        return Some((vec![FormattedText::new(mention, &s[..consumed])], consumed));
+    }
+
+    if !is_name_char(first) {
+        return None;
+    }
+    let word_len = body.find(' ').unwrap_or(body.len());
+    let word = &body[..word_len];
+    let name = word.trim_end_matches(is_punctuation);
+    if name.is_empty() {
+        return None;
+    }
+    let mention = Format::Mention {
+        member_name: name.to_string(),
+    };
+    let mut fragment = vec![FormattedText::new(mention, &s[..name.len() + 1])];
+    if name.len() < word.len() {
+        fragment.push(FormattedText::plain(&word[name.len()..]));
+    }
+    Some((fragment, word_len + 1))
+}
+
+fn is_name_char(c: char) -> bool {
+    c > ' ' && c != '#' && c != '@' && c != '\''
+}
+
+fn phone(s: &str) -> Option<Fragment> {
+    let mut pos = 0;
+
+    if let Some(country) = s.strip_prefix('+') {
+        let digits = digits_len(country);
+        if digits == 0 {
+            return None;
+        }
+        pos = 1 + digits;
+    }
+
+    let sep = phone_separator_len(&s[pos..]);
+    if let Some(code) = s[pos + sep..].strip_prefix('(') {
+        let digits = digits_len(code);
+        if digits > 0 && code[digits..].starts_with(')') {
+            pos += sep + digits + 2;
+        }
+    }
+
+    loop {
+        let sep = phone_separator_len(&s[pos..]);
+        let digits = digits_len(&s[pos + sep..]);
+        if digits == 0 {
+            break;
+        }
+        pos += sep + digits;
+    }
+
+    let phone = &s[..pos];
+    (7..=22)
+        .contains(&phone.chars().count())
+        .then(|| (vec![FormattedText::new(Format::Phone, phone)], phone.len()))
+}
+
+fn phone_separator_len(s: &str) -> usize {
+    usize::from(s.starts_with([' ', '-', '.']))
+}
+
+fn digits_len(s: &str) -> usize {
+    s.len() - s.trim_start_matches(|c: char| c.is_ascii_digit()).len()
+}
+
+fn word(s: &str) -> Fragment {
+    let len = s.find(' ').unwrap_or(s.len());
+    let word = &s[..len];
+    let text = word.trim_end_matches(|c| is_punctuation(c) && c != '/' && c != ')');
+    let punctuation = &word[text.len()..];
+
+    let format = if is_uri(text) {
+        uri_format(text)
+    } else if is_email(text) {
+        Format::Email
+    } else {
+        return (vec![FormattedText::plain(word)], len);
+    };
+
+    let mut fragment = vec![FormattedText::new(format, text)];
+    if !punctuation.is_empty() {
+        fragment.push(FormattedText::plain(punctuation));
+    }
+    (fragment, len)
+}
+
+fn uri_format(s: &str) -> Format {
+    match s.parse::<SimplexLink>() {
+        Ok(link) => Format::SimplexLink {
+            link_type: link.link_type.into(),
+            smp_hosts: link.smp_hosts(),
+            simplex_uri: link.to_simplex_scheme().to_string(),
+        },
+        Err(_) => Format::Uri,
+    }
+}
+
+fn is_uri(s: &str) -> bool {
+    s.chars().count() >= 10
+        && ["http://", "https://", "simplex:/"]
+            .iter()
+            .any(|prefix| s.starts_with(prefix))
+}
+
+fn is_email(s: &str) -> bool {
+    let Some((local, domain)) = s.rsplit_once('@') else {
+        return false;
+    };
+    let local_ok = !local.is_empty()
+        && !local.starts_with('.')
+        && !local.ends_with('.')
+        && local
+            .chars()
+            .all(|c| c.is_alphanumeric() || "!#$%&'*+/=?^_`{|}~.-".contains(c));
+    let labels: Vec<&str> = domain.split('.').collect();
+    let domain_ok = labels.len() >= 2
+        && labels.iter().all(|label| {
+            !label.is_empty()
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label.chars().all(|c| c.is_alphanumeric() || c == '-')
+        });
+    local_ok && domain_ok
+}
+
+/// Haskell's `isPunctuation`: Unicode general category P*. Unlike
+/// `char::is_ascii_punctuation`, this excludes symbols such as `+` or `$`.
+fn is_punctuation(c: char) -> bool {
+    c.general_category_group() == GeneralCategoryGroup::Punctuation
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::markdown::SimplexLinkType;
+
+    fn plain(text: &str) -> FormattedText {
+        FormattedText::plain(text)
+    }
+
+    fn md(format: Format, text: &str) -> FormattedText {
+        FormattedText::new(format, text)
+    }
+
+    fn parse(s: &str) -> Option<Vec<FormattedText>> {
+        parse_markdown(s).formatted_text
+    }
+
+    fn mention(name: &str, text: &str) -> FormattedText {
+        let member_name = name.to_string();
+        md(Format::Mention { member_name }, text)
+    }
+
+    fn colored(color: Color, text: &str) -> FormattedText {
+        md(Format::Colored { color }, text)
+    }
+
+    #[test]
+    fn plain_text() {
+        assert_eq!(parse("this is plain text"), None);
+        assert_eq!(parse(""), None);
+    }
+
+    #[test]
+    fn simple_formats() {
+        let cases = [
+            ("*bold*", Format::Bold, "bold"),
+            ("_italic_", Format::Italic, "italic"),
+            ("~strike~", Format::StrikeThrough, "strike"),
+            ("`snippet`", Format::Snippet, "snippet"),
+            ("#secret#", Format::Secret, "secret"),
+        ];
+        for (input, format, text) in cases {
+            assert_eq!(parse(input), Some(vec![md(format, text)]), "{input}");
+        }
+        assert_eq!(
+            parse("this is *bold formatted* text"),
+            Some(vec![
+                plain("this is "),
+                md(Format::Bold, "bold formatted"),
+                plain(" text"),
+            ])
+        );
+    }
+
+    #[test]
+    fn unclosed_or_padded_markers() {
+        for input in [
+            "this is * unformatted * text",
+            "this is *unformatted text",
+            "this is _ unformatted_ text",
+            "** not bold",
+            "## not secret",
+            "# not secret #",
+        ] {
+            assert_eq!(parse(input), None, "{input}");
+        }
+    }
+
+    #[test]
+    fn secrets_keep_inner_hashes() {
+        assert_eq!(parse("##x##"), Some(vec![md(Format::Secret, "#x#")]));
+    }
+
+    #[test]
+    fn colors() {
+        assert_eq!(
+            parse("!1 red text!"),
+            Some(vec![colored(Color::Red, "red text")])
+        );
+        assert_eq!(
+            parse("!green green! !b blue!"),
+            Some(vec![
+                colored(Color::Green, "green"),
+                plain(" "),
+                colored(Color::Blue, "blue"),
+            ])
+        );
+        assert_eq!(parse("!3\tblue!"), Some(vec![colored(Color::Blue, "blue")]));
+        assert_eq!(parse("!7 not colored!"), None);
+        assert_eq!(parse("!1 not colored !"), None);
+        assert_eq!(parse("!1not colored!"), None);
+    }
+
+    #[test]
+    fn phones() {
+        for input in [
+            "+44 7777 777777",
+            "07777777777",
+            "+1 (555) 123-4567",
+            "1.555.123.4567",
+        ] {
+            assert_eq!(
+                parse(input),
+                Some(vec![md(Format::Phone, input)]),
+                "{input}"
+            );
+        }
+        assert_eq!(
+            parse("call 07777777777."),
+            Some(vec![
+                plain("call "),
+                md(Format::Phone, "07777777777"),
+                plain("."),
+            ])
+        );
+        assert_eq!(parse("12345"), None);
+    }
+
+    #[test]
+    fn emails() {
+        assert_eq!(
+            parse("mail chat@simplex.chat."),
+            Some(vec![
+                plain("mail "),
+                md(Format::Email, "chat@simplex.chat"),
+                plain("."),
+            ])
+        );
+        assert_eq!(parse("not@email"), None);
+    }
+
+    #[test]
+    fn uris_with_trailing_punctuation() {
+        assert_eq!(
+            parse("see https://simplex.chat/docs, or http://example.com/path)."),
+            Some(vec![
+                plain("see "),
+                md(Format::Uri, "https://simplex.chat/docs"),
+                plain(", or "),
+                md(Format::Uri, "http://example.com/path)"),
+                plain("."),
+            ])
+        );
+        assert_eq!(
+            parse("https://example.com/a\u{b7}\u{a7}"),
+            Some(vec![
+                md(Format::Uri, "https://example.com/a"),
+                plain("\u{b7}\u{a7}"),
+            ])
+        );
+        assert_eq!(parse("https://x"), None);
+        assert_eq!(
+            parse("https://github.com/a#readme"),
+            Some(vec![md(Format::Uri, "https://github.com/a#readme")])
+        );
+    }
+
+    #[test]
+    fn simplex_links() {
+        let key = "lJxq0nB3Zl6pX4kVbT2mQ8rYwE5uA1cD9fG7hJ3kL6M";
+        let link = format!("https://smp4.simplex.im/a#{key}");
+        let format = Format::SimplexLink {
+            link_type: SimplexLinkType::Contact,
+            simplex_uri: format!("simplex:/a#{key}?h=smp4.simplex.im"),
+            smp_hosts: vec!["smp4.simplex.im".to_string()],
+        };
+        assert_eq!(parse(&link), Some(vec![md(format, &link)]));
+    }
+
+    #[test]
+    fn mentions() {
+        assert_eq!(
+            parse("hi @alice, and @'alice jones'!"),
+            Some(vec![
+                plain("hi "),
+                mention("alice", "@alice"),
+                plain(", and "),
+                mention("alice jones", "@'alice jones'"),
+                plain("!"),
+            ])
+        );
+        assert_eq!(
+            parse("@bob\u{3002}"),
+            Some(vec![mention("bob", "@bob"), plain("\u{3002}")])
+        );
+        assert_eq!(parse("@ alone"), None);
+        assert_eq!(parse("@#hash"), None);
+    }
+
+    #[test]
+    fn multiline() {
+        assert_eq!(
+            parse("line1\n*bold*\nline3"),
+            Some(vec![
+                plain("line1\n"),
+                md(Format::Bold, "bold"),
+                plain("\nline3"),
+            ])
+        );
+        assert_eq!(
+            parse("hi *a*\n"),
+            Some(vec![plain("hi "), md(Format::Bold, "a")])
+        );
+        assert_eq!(
+            parse("*a*\n\n"),
+            Some(vec![md(Format::Bold, "a"), plain("\n")])
+        );
+    }
+}
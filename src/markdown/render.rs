@@ -0,0 +1,246 @@
+//! Renders a parsed [`FormattedText`] list for terminals and web views.
+
+use super::{Color, Format, FormattedText};
+
+const ANSI_RESET: &str = "\x1b[0m";
+
+/// Renders chunks with ANSI SGR escapes. Control characters and bidi
+/// embedding/override/isolate characters in the message text are dropped, so
+/// a message can't inject its own escape sequences or reorder what follows.
+pub fn ansi(chunks: &[FormattedText]) -> String {
+    let mut out = String::new();
+    for chunk in chunks {
+        let text = chunk.text.chars().filter(|&c| is_safe_for_terminal(c));
+        match chunk.format.as_ref().and_then(ansi_style) {
+            Some(style) => {
+                out.push_str("\x1b[");
+                out.push_str(style);
+                out.push('m');
+                out.extend(text);
+                out.push_str(ANSI_RESET);
+            }
+            None => out.extend(text),
+        }
+    }
+    out
+}
+
+fn is_safe_for_terminal(c: char) -> bool {
+    let control = c.is_control() && c != '\n' && c != '\t';
+    !control && !is_bidi_control(c)
+}
+
+/// Embedding, override and isolate controls, which let text reorder how the
+/// characters after it are displayed.
+fn is_bidi_control(c: char) -> bool {
+    matches!(c, '\u{202a}'..='\u{202e}' | '\u{2066}'..='\u{2069}')
+}
+
+fn ansi_style(format: &Format) -> Option<&'static str> {
+    let style = match format {
+        Format::Bold | Format::Mention { .. } => "1",
+        Format::Italic => "3",
+        Format::StrikeThrough => "9",
+        Format::Snippet => "2",
+        Format::Secret => "8",
+        Format::Colored { color } => match color {
+            Color::Black => "30",
+            Color::Red => "31",
+            Color::Green => "32",
+            Color::Yellow => "33",
+            Color::Blue => "34",
+            Color::Magenta => "35",
+            Color::Cyan => "36",
+            Color::White => "37",
+            Color::Unknown(_) => return None,
+        },
+        Format::Uri | Format::SimplexLink { .. } | Format::Email | Format::Phone => "4;34",
+        Format::Unknown(_) => return None,
+    };
+    Some(style)
+}
+
+/// Renders chunks as an HTML fragment. All message text is escaped, bidi
+/// controls are dropped as in [`ansi`], and only `http`, `https`, `simplex`,
+/// `mailto` and `tel` links are emitted as anchors.
+pub fn html(chunks: &[FormattedText]) -> String {
+    let mut out = String::new();
+    for chunk in chunks {
+        let text = escape_html(&chunk.text).replace('\n', "<br>");
+        let Some(format) = &chunk.format else {
+            out.push_str(&text);
+            continue;
+        };
+        match format {
+            Format::Bold => wrap(&mut out, "<b>", &text, "</b>"),
+            Format::Italic => wrap(&mut out, "<i>", &text, "</i>"),
+            Format::StrikeThrough => wrap(&mut out, "<s>", &text, "</s>"),
+            Format::Snippet => wrap(&mut out, "<code>", &text, "</code>"),
+            Format::Secret => wrap(&mut out, "<span class=\"secret\">", &text, "</span>"),
+            Format::Colored { color } => match color_name(color) {
+                Some(name) => {
+                    let open = format!("<span style=\"color: {name}\">");
+                    wrap(&mut out, &open, &text, "</span>");
+                }
+                None => out.push_str(&text),
+            },
+            Format::Mention { .. } => wrap(&mut out, "<span class=\"mention\">", &text, "</span>"),
+            Format::Uri | Format::SimplexLink { .. } => link(&mut out, &chunk.text, &text),
+            Format::Email => link(&mut out, &format!("mailto:{}", chunk.text), &text),
+            Format::Phone => {
+                let number: String = chunk
+                    .text
+                    .chars()
+                    .filter(|c| c.is_ascii_digit() || *c == '+')
+                    .collect();
+                link(&mut out, &format!("tel:{number}"), &text);
+            }
+            Format::Unknown(_) => out.push_str(&text),
+        }
+    }
+    out
+}
+
+fn wrap(out: &mut String, open: &str, text: &str, close: &str) {
+    out.push_str(open);
+    out.push_str(text);
+    out.push_str(close);
+}
+
+fn link(out: &mut String, href: &str, text: &str) {
+    let safe = ["http://", "https://", "simplex:/", "mailto:", "tel:"]
+        .iter()
+        .any(|scheme| href.starts_with(scheme));
+    if safe {
+        let open = format!(
+            "<a href=\"{}\" rel=\"noopener noreferrer\">",
+            escape_html(href)
+        );
+        wrap(out, &open, text, "</a>");
+    } else {
+        out.push_str(text);
+    }
+}
+
+fn color_name(color: &Color) -> Option<&'static str> {
+    let name = match color {
+        Color::Red => "red",
+        Color::Green => "green",
+        Color::Blue => "blue",
+        Color::Yellow => "yellow",
+        Color::Cyan => "cyan",
+        Color::Magenta => "magenta",
+        Color::Black => "black",
+        Color::White => "white",
+        Color::Unknown(_) => return None,
+    };
+    Some(name)
+}
+
+fn escape_html(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c if is_bidi_control(c) => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ansi_strips_escape_sequences() {
+        let chunks = [
+            FormattedText::plain("a\x1b[31mb\x07c\u{9b}d\n"),
+            FormattedText::new(Format::Bold, "\x1b]0;title\x07e"),
+        ];
+        assert_eq!(ansi(&chunks), "a[31mbcd\n\x1b[1m]0;titlee\x1b[0m");
+    }
+
+    #[test]
+    fn ansi_strips_bidi_controls() {
+        let text = "x\u{202e}gnp.exe\u{202c}\u{2066}y\u{2069}";
+        assert_eq!(ansi(&[FormattedText::plain(text)]), "xgnp.exey");
+    }
+
+    #[test]
+    fn ansi_styles_formats() {
+        let chunks = [
+            FormattedText::plain("hi "),
+            FormattedText::new(Format::Colored { color: Color::Red }, "red"),
+        ];
+        assert_eq!(ansi(&chunks), "hi \x1b[31mred\x1b[0m");
+    }
+
+    #[test]
+    fn html_escapes_text() {
+        let chunks = [
+            FormattedText::plain("<script>alert('x')</script> & \"q\"\n"),
+            FormattedText::new(Format::Bold, "<b>"),
+        ];
+        assert_eq!(
+            html(&chunks),
+            "&lt;script&gt;alert(&#39;x&#39;)&lt;/script&gt; &amp; &quot;q&quot;<br><b>&lt;b&gt;</b>"
+        );
+    }
+
+    #[test]
+    fn html_escapes_quotes_in_href() {
+        let chunks = [FormattedText::new(
+            Format::Uri,
+            "https://x.com/\"onmouseover=\"alert(1)",
+        )];
+        assert_eq!(
+            html(&chunks),
+            "<a href=\"https://x.com/&quot;onmouseover=&quot;alert(1)\" rel=\"noopener noreferrer\">\
+             https://x.com/&quot;onmouseover=&quot;alert(1)</a>"
+        );
+    }
+
+    #[test]
+    fn html_strips_bidi_controls() {
+        let chunks = [
+            FormattedText::plain("x\u{202e}gnp.exe\u{202c}\u{2066}y\u{2069}"),
+            FormattedText::new(Format::Uri, "https://a.com/\u{202e}fdp.exe"),
+        ];
+        assert_eq!(
+            html(&chunks),
+            "xgnp.exey<a href=\"https://a.com/fdp.exe\" rel=\"noopener noreferrer\">\
+             https://a.com/fdp.exe</a>"
+        );
+    }
+
+    #[test]
+    fn html_drops_unsafe_link_schemes() {
+        let chunks = [
+            FormattedText::new(Format::Uri, "javascript:alert(1)"),
+            FormattedText::new(Format::Uri, "data:text/html,<script>"),
+        ];
+        assert_eq!(
+            html(&chunks),
+            "javascript:alert(1)data:text/html,&lt;script&gt;"
+        );
+    }
+
+    #[test]
+    fn html_links_emails_and_phones() {
+        let chunks = [
+            FormattedText::new(Format::Email, "a@b.co"),
+            FormattedText::new(Format::Phone, "+1 (555) 123-4567"),
+        ];
+        assert_eq!(
+            html(&chunks),
+            "<a href=\"mailto:a@b.co\" rel=\"noopener noreferrer\">a@b.co</a>\
+             <a href=\"tel:+15551234567\" rel=\"noopener noreferrer\">+1 (555) 123-4567</a>"
+        );
+    }
+}
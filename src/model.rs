@@ -0,0 +1,792 @@
+//! Core chatcore types, as they appear in its JSON responses and events.
+//!
+//! Only the fields clients commonly need are modelled; everything else in
+//! chatcore's JSON is ignored. Timestamps are kept as the ISO 8601 strings
+//! chatcore sends. Enum values added to chatcore later are kept as sent, in
+//! the `Unknown`/`Other` variants, so they serialize back unchanged. A known
+//! tag with fields this crate can't parse ends up there too.
+
+use serde::{Deserialize, Serialize};
+
+use crate::markdown::FormattedText;
+
+pub type Timestamp = String;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct User {
+    pub user_id: i64,
+    pub agent_user_id: i64,
+    pub user_contact_id: i64,
+    pub local_display_name: String,
+    pub profile: LocalProfile,
+    pub active_user: bool,
+    #[serde(default)]
+    pub show_ntfs: bool,
+    #[serde(default)]
+    pub send_rcpts_contacts: bool,
+    #[serde(default)]
+    pub send_rcpts_small_groups: bool,
+    /// Set when the profile is hidden behind a password.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub view_pwd_hash: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LocalProfile {
+    pub profile_id: i64,
+    pub display_name: String,
+    #[serde(default)]
+    pub full_name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub contact_link: Option<String>,
+    #[serde(default)]
+    pub local_alias: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Contact {
+    pub contact_id: i64,
+    pub local_display_name: String,
+    pub profile: LocalProfile,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub active_conn: Option<Connection>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub via_group: Option<i64>,
+    #[serde(default)]
+    pub contact_used: bool,
+    pub contact_status: ContactStatus,
+    #[serde(default)]
+    pub chat_settings: ChatSettings,
+    pub created_at: Timestamp,
+    pub updated_at: Timestamp,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chat_ts: Option<Timestamp>,
+    #[serde(default)]
+    pub chat_deleted: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ContactStatus {
+    Active,
+    Deleted,
+    DeletedByUser,
+    #[serde(untagged)]
+    Unknown(String),
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChatSettings {
+    #[serde(default)]
+    pub enable_ntfs: MsgFilter,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub send_rcpts: Option<bool>,
+    #[serde(default)]
+    pub favorite: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MsgFilter {
+    None,
+    #[default]
+    All,
+    Mentions,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Connection {
+    pub conn_id: i64,
+    pub agent_conn_id: String,
+    pub conn_level: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub via_contact: Option<i64>,
+    #[serde(default)]
+    pub via_group_link: bool,
+    pub conn_type: ConnType,
+    pub conn_status: ConnStatus,
+    #[serde(default)]
+    pub contact_conn_initiated: bool,
+    #[serde(default)]
+    pub local_alias: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub entity_id: Option<i64>,
+    #[serde(default)]
+    pub pq_encryption: bool,
+    #[serde(default)]
+    pub auth_err_counter: i64,
+    pub created_at: Timestamp,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ConnType {
+    #[serde(rename = "contact")]
+    Contact,
+    #[serde(rename = "member")]
+    Member,
+    #[serde(rename = "rcv_file")]
+    RcvFile,
+    #[serde(rename = "snd_file")]
+    SndFile,
+    #[serde(rename = "user_contact")]
+    UserContact,
+    #[serde(untagged)]
+    Unknown(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ConnStatus {
+    New,
+    Prepared,
+    Joined,
+    Requested,
+    Accepted,
+    SndReady,
+    Ready,
+    Deleted,
+    #[serde(untagged)]
+    Unknown(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GroupInfo {
+    pub group_id: i64,
+    pub local_display_name: String,
+    pub group_profile: GroupProfile,
+    #[serde(default)]
+    pub local_alias: String,
+    /// The user's own membership in the group.
+    pub membership: GroupMember,
+    #[serde(default)]
+    pub chat_settings: ChatSettings,
+    pub created_at: Timestamp,
+    pub updated_at: Timestamp,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chat_ts: Option<Timestamp>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GroupProfile {
+    pub display_name: String,
+    #[serde(default)]
+    pub full_name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GroupMember {
+    pub group_member_id: i64,
+    pub group_id: i64,
+    pub member_id: String,
+    pub member_role: GroupMemberRole,
+    pub member_category: GroupMemberCategory,
+    pub member_status: GroupMemberStatus,
+    #[serde(default)]
+    pub blocked_by_admin: bool,
+    pub local_display_name: String,
+    pub member_profile: LocalProfile,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub member_contact_id: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub active_conn: Option<Connection>,
+    pub created_at: Timestamp,
+    pub updated_at: Timestamp,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum GroupMemberRole {
+    Observer,
+    Author,
+    Member,
+    Moderator,
+    Admin,
+    Owner,
+    /// Roles added to chatcore later. Ordered below every known role, so
+    /// permission checks like `role >= Admin` fail closed.
+    #[serde(untagged)]
+    Unknown(String),
+}
+
+impl GroupMemberRole {
+    fn rank(&self) -> u8 {
+        match self {
+            Self::Unknown(_) => 0,
+            Self::Observer => 1,
+            Self::Author => 2,
+            Self::Member => 3,
+            Self::Moderator => 4,
+            Self::Admin => 5,
+            Self::Owner => 6,
+        }
+    }
+}
+
+impl PartialOrd for GroupMemberRole {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for GroupMemberRole {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.rank()
+            .cmp(&other.rank())
+            .then_with(|| match (self, other) {
+                (Self::Unknown(a), Self::Unknown(b)) => a.cmp(b),
+                _ => std::cmp::Ordering::Equal,
+            })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum GroupMemberCategory {
+    User,
+    Invitee,
+    Host,
+    Pre,
+    Post,
+    #[serde(untagged)]
+    Unknown(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum GroupMemberStatus {
+    Rejected,
+    #[serde(rename = "pending_approval")]
+    PendingApproval,
+    Removed,
+    Left,
+    #[serde(rename = "pending_review")]
+    PendingReview,
+    Deleted,
+    /// chatcore's own "unknown" status, for members known only from
+    /// messages forwarded by other members.
+    #[serde(rename = "unknown")]
+    MemUnknown,
+    Invited,
+    Introduced,
+    IntroInv,
+    Accepted,
+    Announced,
+    Connected,
+    Complete,
+    Creator,
+    /// Statuses added to chatcore later.
+    #[serde(untagged)]
+    Other(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NoteFolder {
+    pub note_folder_id: i64,
+    pub user_id: i64,
+    pub created_at: Timestamp,
+    pub updated_at: Timestamp,
+    #[serde(default)]
+    pub favorite: bool,
+    #[serde(default)]
+    pub unread: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum ChatInfo {
+    Direct {
+        contact: Box<Contact>,
+    },
+    #[serde(rename_all = "camelCase")]
+    Group {
+        group_info: Box<GroupInfo>,
+    },
+    #[serde(rename_all = "camelCase")]
+    Local {
+        note_folder: NoteFolder,
+    },
+    /// Contact requests, pending connections and chat types added later.
+    #[serde(untagged)]
+    Other(serde_json::Value),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AChatItem {
+    pub chat_info: ChatInfo,
+    pub chat_item: ChatItem,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChatItem {
+    pub chat_dir: CIDirection,
+    pub meta: CIMeta,
+    pub content: CIContent,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub formatted_text: Option<Vec<FormattedText>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quoted_item: Option<serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum CIDirection {
+    DirectSnd,
+    DirectRcv,
+    GroupSnd,
+    #[serde(rename_all = "camelCase")]
+    GroupRcv {
+        group_member: Box<GroupMember>,
+    },
+    LocalSnd,
+    LocalRcv,
+}
+
+impl CIDirection {
+    pub fn is_sent(&self) -> bool {
+        matches!(self, Self::DirectSnd | Self::GroupSnd | Self::LocalSnd)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CIMeta {
+    pub item_id: i64,
+    pub item_ts: Timestamp,
+    pub item_text: String,
+    pub item_status: CIStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub item_shared_msg_id: Option<String>,
+    #[serde(default)]
+    pub item_edited: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub item_deleted: Option<serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub item_live: Option<bool>,
+    #[serde(default)]
+    pub user_mention: bool,
+    #[serde(default)]
+    pub deletable: bool,
+    #[serde(default)]
+    pub editable: bool,
+    pub created_at: Timestamp,
+    pub updated_at: Timestamp,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum CIStatus {
+    SndNew,
+    #[serde(rename_all = "camelCase")]
+    SndSent {
+        snd_progress: SndCIStatusProgress,
+    },
+    #[serde(rename_all = "camelCase")]
+    SndRcvd {
+        msg_rcpt_status: MsgReceiptStatus,
+        snd_progress: SndCIStatusProgress,
+    },
+    SndErrorAuth,
+    /// `agent_error` is chatcore's `SndError`, kept as raw JSON.
+    #[serde(rename_all = "camelCase")]
+    SndError {
+        agent_error: serde_json::Value,
+    },
+    #[serde(rename_all = "camelCase")]
+    SndWarning {
+        agent_error: serde_json::Value,
+    },
+    RcvNew,
+    RcvRead,
+    Invalid {
+        text: String,
+    },
+    #[serde(untagged)]
+    Unknown(serde_json::Value),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SndCIStatusProgress {
+    Partial,
+    Complete,
+    #[serde(untagged)]
+    Unknown(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MsgReceiptStatus {
+    Ok,
+    BadMsgHash,
+    #[serde(untagged)]
+    Unknown(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum CIContent {
+    #[serde(rename_all = "camelCase")]
+    SndMsgContent {
+        msg_content: MsgContent,
+    },
+    #[serde(rename_all = "camelCase")]
+    RcvMsgContent {
+        msg_content: MsgContent,
+    },
+    SndDeleted,
+    RcvDeleted,
+    /// Calls, group and connection events, feature changes, integrity
+    /// errors and content kinds added to chatcore later.
+    #[serde(untagged)]
+    Other(serde_json::Value),
+}
+
+impl CIContent {
+    pub fn msg_content(&self) -> Option<&MsgContent> {
+        match self {
+            Self::SndMsgContent { msg_content } | Self::RcvMsgContent { msg_content } => {
+                Some(msg_content)
+            }
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum MsgContent {
+    Text {
+        text: String,
+    },
+    Link {
+        text: String,
+        preview: LinkPreview,
+    },
+    /// `image` is a base64 `data:` URI preview.
+    Image {
+        text: String,
+        image: String,
+    },
+    Video {
+        text: String,
+        image: String,
+        duration: u32,
+    },
+    Voice {
+        text: String,
+        duration: u32,
+    },
+    File {
+        text: String,
+    },
+    #[serde(untagged)]
+    Unknown(serde_json::Value),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LinkPreview {
+    pub uri: String,
+    pub title: String,
+    pub description: String,
+    pub image: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PROFILE: &str = r#"{
+        "profileId": 2,
+        "displayName": "alice",
+        "fullName": "Alice",
+        "preferences": {"calls": {"allow": "yes"}},
+        "localAlias": ""
+    }"#;
+
+    fn member(id: i64, role: &str, category: &str, status: &str) -> String {
+        format!(
+            r#"{{
+                "groupMemberId": {id},
+                "groupId": 1,
+                "memberId": "AQID",
+                "memberRole": "{role}",
+                "memberCategory": "{category}",
+                "memberStatus": "{status}",
+                "memberSettings": {{"showMessages": true}},
+                "blockedByAdmin": false,
+                "invitedBy": {{"type": "user"}},
+                "localDisplayName": "alice",
+                "memberProfile": {PROFILE},
+                "memberContactId": 3,
+                "createdAt": "2024-05-01T10:00:00.000000Z",
+                "updatedAt": "2024-05-01T10:00:00.000000Z"
+            }}"#
+        )
+    }
+
+    fn meta(status: &str) -> String {
+        format!(
+            r#"{{
+                "itemId": 42,
+                "itemTs": "2024-05-01T10:00:00.000000Z",
+                "itemText": "hello *there*",
+                "itemStatus": {status},
+                "itemSharedMsgId": "bWVzc2FnZQ==",
+                "itemEdited": false,
+                "userMention": false,
+                "deletable": true,
+                "editable": true,
+                "timed": null,
+                "createdAt": "2024-05-01T10:00:00.000000Z",
+                "updatedAt": "2024-05-01T10:00:01.000000Z"
+            }}"#
+        )
+    }
+
+    #[test]
+    fn direct_chat_item() {
+        let json = format!(
+            r#"{{
+                "chatInfo": {{
+                    "type": "direct",
+                    "contact": {{
+                        "contactId": 3,
+                        "localDisplayName": "alice",
+                        "profile": {PROFILE},
+                        "activeConn": {{
+                            "connId": 7,
+                            "agentConnId": "Y29ubg==",
+                            "connChatVersion": 14,
+                            "peerChatVRange": {{"minVersion": 1, "maxVersion": 14}},
+                            "connLevel": 0,
+                            "viaGroupLink": false,
+                            "connType": "contact",
+                            "connStatus": "ready",
+                            "contactConnInitiated": false,
+                            "localAlias": "",
+                            "entityId": 3,
+                            "pqSupport": true,
+                            "pqEncryption": true,
+                            "authErrCounter": 0,
+                            "quotaErrCounter": 0,
+                            "createdAt": "2024-05-01T09:00:00.000000Z"
+                        }},
+                        "contactUsed": true,
+                        "contactStatus": "active",
+                        "chatSettings": {{"enableNtfs": "all", "favorite": false}},
+                        "userPreferences": {{}},
+                        "mergedPreferences": {{}},
+                        "createdAt": "2024-05-01T09:00:00.000000Z",
+                        "updatedAt": "2024-05-01T09:00:00.000000Z",
+                        "chatTs": "2024-05-01T10:00:00.000000Z",
+                        "chatDeleted": false
+                    }}
+                }},
+                "chatItem": {{
+                    "chatDir": {{"type": "directSnd"}},
+                    "meta": {},
+                    "content": {{
+                        "type": "sndMsgContent",
+                        "msgContent": {{"type": "text", "text": "hello *there*"}}
+                    }},
+                    "mentions": {{}},
+                    "formattedText": [
+                        {{"text": "hello "}},
+                        {{"format": {{"type": "bold"}}, "text": "there"}}
+                    ],
+                    "reactions": []
+                }}
+            }}"#,
+            meta(r#"{"type": "sndRcvd", "msgRcptStatus": "ok", "sndProgress": "complete"}"#)
+        );
+        let item: AChatItem = serde_json::from_str(&json).unwrap();
+
+        let ChatInfo::Direct { contact } = &item.chat_info else {
+            panic!("expected a direct chat: {:?}", item.chat_info);
+        };
+        assert_eq!(contact.contact_status, ContactStatus::Active);
+        let conn = contact.active_conn.as_ref().unwrap();
+        assert_eq!(conn.conn_type, ConnType::Contact);
+        assert_eq!(conn.conn_status, ConnStatus::Ready);
+        assert!(item.chat_item.chat_dir.is_sent());
+        assert_eq!(
+            item.chat_item.meta.item_status,
+            CIStatus::SndRcvd {
+                msg_rcpt_status: MsgReceiptStatus::Ok,
+                snd_progress: SndCIStatusProgress::Complete,
+            }
+        );
+        assert_eq!(
+            item.chat_item.content.msg_content(),
+            Some(&MsgContent::Text {
+                text: "hello *there*".into()
+            })
+        );
+        assert_eq!(item.chat_item.formatted_text.map(|f| f.len()), Some(2));
+    }
+
+    #[test]
+    fn group_chat_item() {
+        let json = format!(
+            r#"{{
+                "chatInfo": {{
+                    "type": "group",
+                    "groupInfo": {{
+                        "groupId": 1,
+                        "localDisplayName": "team",
+                        "groupProfile": {{
+                            "displayName": "team",
+                            "fullName": "",
+                            "groupPreferences": {{"directMessages": {{"enable": "on"}}}}
+                        }},
+                        "localAlias": "",
+                        "fullGroupPreferences": {{}},
+                        "membership": {},
+                        "chatSettings": {{"enableNtfs": "mentions", "favorite": true}},
+                        "createdAt": "2024-05-01T09:00:00.000000Z",
+                        "updatedAt": "2024-05-01T09:00:00.000000Z",
+                        "chatTs": "2024-05-01T10:00:00.000000Z",
+                        "userMemberProfileSentAt": "2024-05-01T09:00:00.000000Z"
+                    }}
+                }},
+                "chatItem": {{
+                    "chatDir": {{"type": "groupRcv", "groupMember": {}}},
+                    "meta": {},
+                    "content": {{
+                        "type": "rcvMsgContent",
+                        "msgContent": {{"type": "text", "text": "hello *there*"}}
+                    }},
+                    "mentions": {{}},
+                    "reactions": []
+                }}
+            }}"#,
+            member(1, "member", "user", "connected"),
+            member(5, "admin", "post", "pending_review"),
+            meta(r#"{"type": "rcvNew"}"#)
+        );
+        let item: AChatItem = serde_json::from_str(&json).unwrap();
+
+        let ChatInfo::Group { group_info } = &item.chat_info else {
+            panic!("expected a group chat: {:?}", item.chat_info);
+        };
+        assert_eq!(group_info.membership.member_role, GroupMemberRole::Member);
+        assert_eq!(
+            group_info.membership.member_status,
+            GroupMemberStatus::Connected
+        );
+        assert_eq!(group_info.chat_settings.enable_ntfs, MsgFilter::Mentions);
+        let CIDirection::GroupRcv { group_member } = &item.chat_item.chat_dir else {
+            panic!("expected a received item: {:?}", item.chat_item.chat_dir);
+        };
+        assert_eq!(group_member.member_role, GroupMemberRole::Admin);
+        assert_eq!(group_member.member_category, GroupMemberCategory::Post);
+        assert_eq!(group_member.member_status, GroupMemberStatus::PendingReview);
+        assert_eq!(item.chat_item.meta.item_status, CIStatus::RcvNew);
+    }
+
+    #[test]
+    fn unknown_enum_values_fall_back() {
+        let json = member(5, "superuser", "future", "pending_something");
+        let member: GroupMember = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            member.member_role,
+            GroupMemberRole::Unknown("superuser".into())
+        );
+        assert!(member.member_role < GroupMemberRole::Observer);
+        assert_eq!(
+            member.member_category,
+            GroupMemberCategory::Unknown("future".into())
+        );
+        assert_eq!(
+            member.member_status,
+            GroupMemberStatus::Other("pending_something".into())
+        );
+
+        let status: GroupMemberStatus = serde_json::from_str(r#""unknown""#).unwrap();
+        assert_eq!(status, GroupMemberStatus::MemUnknown);
+
+        let pending = serde_json::json!({"type": "sndPending", "retries": 1});
+        let status: CIStatus = serde_json::from_value(pending.clone()).unwrap();
+        assert_eq!(status, CIStatus::Unknown(pending));
+        let status: CIStatus = serde_json::from_str(
+            r#"{"type": "sndRcvd", "msgRcptStatus": "later", "sndProgress": "later"}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            status,
+            CIStatus::SndRcvd {
+                msg_rcpt_status: MsgReceiptStatus::Unknown("later".into()),
+                snd_progress: SndCIStatusProgress::Unknown("later".into()),
+            }
+        );
+    }
+
+    #[test]
+    fn unknown_values_round_trip() {
+        let content = serde_json::json!({
+            "type": "rcvGroupEvent",
+            "rcvGroupEvent": {"type": "memberRole", "groupMemberId": 5, "role": "superuser"}
+        });
+        let json = format!(
+            r#"{{
+                "chatInfo": {{"type": "contactRequest", "contactRequest": {{"contactRequestId": 1}}}},
+                "chatItem": {{
+                    "chatDir": {{"type": "groupRcv", "groupMember": {}}},
+                    "meta": {},
+                    "content": {content}
+                }}
+            }}"#,
+            member(5, "superuser", "future", "pending_something"),
+            meta(r#"{"type": "sndPending", "retries": 1}"#)
+        );
+        let item: AChatItem = serde_json::from_str(&json).unwrap();
+        let value = serde_json::to_value(&item).unwrap();
+
+        assert_eq!(
+            value["chatInfo"],
+            serde_json::json!({"type": "contactRequest", "contactRequest": {"contactRequestId": 1}})
+        );
+        let member = &value["chatItem"]["chatDir"]["groupMember"];
+        assert_eq!(member["memberRole"], "superuser");
+        assert_eq!(member["memberCategory"], "future");
+        assert_eq!(member["memberStatus"], "pending_something");
+        assert_eq!(
+            value["chatItem"]["meta"]["itemStatus"],
+            serde_json::json!({"type": "sndPending", "retries": 1})
+        );
+        assert_eq!(value["chatItem"]["content"], content);
+
+        let reparsed: AChatItem = serde_json::from_value(value).unwrap();
+        assert_eq!(reparsed, item);
+    }
+
+    #[test]
+    fn unknown_msg_content_round_trips() {
+        let content = serde_json::json!({
+            "type": "sndMsgContent",
+            "msgContent": {"type": "chat", "text": "", "chatLink": {"type": "group"}}
+        });
+        let parsed: CIContent = serde_json::from_value(content.clone()).unwrap();
+        assert!(matches!(parsed.msg_content(), Some(MsgContent::Unknown(_))));
+        assert_eq!(serde_json::to_value(&parsed).unwrap(), content);
+    }
+}
@@ -0,0 +1,306 @@
+//! Database passphrase generation and strength estimation.
+//!
+//! Generated passphrases are drawn from the EFF large wordlist
+//! (<https://www.eff.org/dice>, CC BY 3.0 US).
+
+use std::sync::OnceLock;
+
+const WORDLIST: &str = include_str!("passphrase/eff_large_wordlist.txt");
+
+/// Six words give about 77 bits of entropy.
+pub const DEFAULT_WORDS: usize = 6;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Strength {
+    VeryWeak,
+    Weak,
+    Fair,
+    Strong,
+    VeryStrong,
+}
+
+impl Strength {
+    pub fn from_entropy(bits: f64) -> Self {
+        match bits {
+            b if b < 28. => Self::VeryWeak,
+            b if b < 36. => Self::Weak,
+            b if b < 60. => Self::Fair,
+            b if b < 128. => Self::Strong,
+            _ => Self::VeryStrong,
+        }
+    }
+}
+
+/// Generates a passphrase of `words` random wordlist entries joined by `-`.
+pub fn generate(words: usize) -> Result<String, getrandom::Error> {
+    generate_with_separator(words, "-")
+}
+
+pub fn generate_with_separator(words: usize, separator: &str) -> Result<String, getrandom::Error> {
+    let list = wordlist();
+    let mut chosen = Vec::with_capacity(words);
+    for _ in 0..words {
+        chosen.push(list[random_index(list.len())?]);
+    }
+    Ok(chosen.join(separator))
+}
+
+/// Estimates the entropy of `passphrase` in bits.
+///
+/// The passphrase is split into alphanumeric parts, and each part is costed
+/// by how an attacker would guess it:
+///
+/// - a wordlist entry counts as one random word;
+/// - a part that repeats an earlier one adds nothing;
+/// - wordlist entries inside a letter stem, found once common digit
+///   substitutions (`0` for `o`, `4` for `a`, ...) are undone, count as one
+///   word each, and the remaining letters by character. Each substitution,
+///   capitals and any trailing digits add to that;
+/// - anything else counts by its length and the character classes of the
+///   whole passphrase, ignoring immediate repeats of a character.
+///
+/// Separators don't add entropy.
+pub fn estimate_entropy(passphrase: &str) -> f64 {
+    let pool = pool_size(passphrase);
+    if pool == 0 {
+        return 0.;
+    }
+    let char_bits = f64::from(pool).log2();
+    let word_bits = (wordlist().len() as f64).log2();
+
+    let parts: Vec<&str> = passphrase
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .collect();
+    if parts.is_empty() {
+        return distinct_run_length(passphrase) as f64 * char_bits;
+    }
+    let lower: Vec<String> = parts.iter().map(|part| part.to_lowercase()).collect();
+
+    // A few entries (`t-shirt`, `yo-yo`) contain a hyphen, so a word may span
+    // two adjacent parts; take the cheapest way to cover all of them.
+    let mut best = vec![0.; parts.len() + 1];
+    for end in 1..=parts.len() {
+        let i = end - 1;
+        let part_bits = if lower[..i].contains(&lower[i]) {
+            0.
+        } else if is_list_word(&lower[i]) {
+            word_bits
+        } else {
+            part_entropy(parts[i], char_bits, word_bits)
+        };
+        best[end] = best[i] + part_bits;
+        if i >= 1 && is_list_word(&format!("{}-{}", lower[i - 1], lower[i])) {
+            best[end] = best[end].min(best[i - 1] + word_bits);
+        }
+    }
+    best[parts.len()]
+}
+
+/// Entropy of an alphanumeric part that isn't a wordlist entry.
+fn part_entropy(part: &str, char_bits: f64, word_bits: f64) -> f64 {
+    let random = distinct_run_length(part) as f64 * char_bits;
+
+    let stem = part.trim_end_matches(|c: char| c.is_ascii_digit());
+    let suffix_bits = (part.len() - stem.len()) as f64 * 10f64.log2();
+    let mut substitutions = 0;
+    let plain: Vec<char> = stem
+        .chars()
+        .map(|c| match unleet(c) {
+            Some(letter) => {
+                substitutions += 1;
+                letter
+            }
+            None => c.to_lowercase().next().unwrap_or(c),
+        })
+        .collect();
+    if plain.is_empty() || !plain.iter().all(|c| c.is_alphabetic()) {
+        return random;
+    }
+
+    let capitals = if stem.chars().any(char::is_uppercase) {
+        1.
+    } else {
+        0.
+    };
+    let guessed =
+        segment_entropy(&plain, char_bits, word_bits) + capitals + f64::from(substitutions);
+    (guessed + suffix_bits).min(random)
+}
+
+/// Cheapest cover of `letters` by wordlist entries, each counting as one
+/// word, and single letters, each counting as one character.
+fn segment_entropy(letters: &[char], char_bits: f64, word_bits: f64) -> f64 {
+    let longest = wordlist().iter().map(|word| word.len()).max().unwrap_or(0);
+    let mut best = vec![0.; letters.len() + 1];
+    let mut word = String::new();
+    for end in 1..=letters.len() {
+        best[end] = best[end - 1] + char_bits;
+        for start in end.saturating_sub(longest)..end {
+            word.clear();
+            word.extend(&letters[start..end]);
+            if is_list_word(&word) {
+                best[end] = best[end].min(best[start] + word_bits);
+            }
+        }
+    }
+    best[letters.len()]
+}
+
+fn unleet(c: char) -> Option<char> {
+    let letter = match c {
+        '0' => 'o',
+        '1' => 'l',
+        '3' => 'e',
+        '4' => 'a',
+        '5' => 's',
+        '7' => 't',
+        _ => return None,
+    };
+    Some(letter)
+}
+
+pub fn strength(passphrase: &str) -> Strength {
+    Strength::from_entropy(estimate_entropy(passphrase))
+}
+
+fn pool_size(passphrase: &str) -> u32 {
+    let (mut lower, mut upper, mut digit, mut symbol, mut letter, mut other) =
+        (false, false, false, false, false, false);
+    for c in passphrase.chars() {
+        match c {
+            'a'..='z' => lower = true,
+            'A'..='Z' => upper = true,
+            '0'..='9' => digit = true,
+            c if c.is_ascii() => symbol = true,
+            // Text in another script is drawn from that script's alphabet,
+            // not from every character outside ASCII.
+            c if c.is_alphabetic() => letter = true,
+            _ => other = true,
+        }
+    }
+    [
+        (lower, 26),
+        (upper, 26),
+        (digit, 10),
+        (symbol, 33),
+        (letter, 33),
+        (other, 100),
+    ]
+    .iter()
+    .filter(|(used, _)| *used)
+    .map(|(_, size)| size)
+    .sum()
+}
+
+fn distinct_run_length(s: &str) -> usize {
+    let mut previous = None;
+    s.chars()
+        .filter(|&c| previous.replace(c) != Some(c))
+        .count()
+}
+
+fn is_list_word(word: &str) -> bool {
+    wordlist().binary_search(&word).is_ok()
+}
+
+fn wordlist() -> &'static [&'static str] {
+    static LIST: OnceLock<Vec<&'static str>> = OnceLock::new();
+    LIST.get_or_init(|| WORDLIST.lines().collect())
+}
+
+/// Uniform index below `len`, rejecting the biased top of the `u32` range.
+fn random_index(len: usize) -> Result<usize, getrandom::Error> {
+    let len = len as u32;
+    let zone = u32::MAX - u32::MAX % len;
+    loop {
+        let n = getrandom::u32()?;
+        if n < zone {
+            return Ok((n % len) as usize);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn word_bits() -> f64 {
+        7776f64.log2()
+    }
+
+    #[test]
+    fn generated_passphrases_use_list_words() {
+        for words in [1, 4, DEFAULT_WORDS] {
+            let passphrase = generate(words).unwrap();
+            let parts: Vec<&str> = passphrase.split('-').collect();
+            // `t-shirt` and friends contain the separator themselves.
+            let mut count = 0;
+            let mut rest = &parts[..];
+            while !rest.is_empty() {
+                if rest.len() >= 2 && is_list_word(&format!("{}-{}", rest[0], rest[1])) {
+                    rest = &rest[2..];
+                } else {
+                    assert!(is_list_word(rest[0]), "{passphrase}");
+                    rest = &rest[1..];
+                }
+                count += 1;
+            }
+            assert_eq!(count, words, "{passphrase}");
+        }
+    }
+
+    #[test]
+    fn generated_passphrase_entropy() {
+        // Hyphenated entries like `yo-yo` are split into parts and costed
+        // separately, so keep to plain words here.
+        let passphrase = loop {
+            let passphrase = generate_with_separator(DEFAULT_WORDS, " ").unwrap();
+            if !passphrase.contains('-') {
+                break passphrase;
+            }
+        };
+        let mut distinct: Vec<&str> = passphrase.split(' ').collect();
+        distinct.sort_unstable();
+        distinct.dedup();
+        let expected = distinct.len() as f64 * word_bits();
+        assert!((estimate_entropy(&passphrase) - expected).abs() < 1e-9);
+        assert_eq!(strength(&passphrase), Strength::Strong);
+    }
+
+    #[test]
+    fn repeated_words_count_once() {
+        let once = estimate_entropy("correct");
+        assert!((once - word_bits()).abs() < 1e-9);
+        assert_eq!(estimate_entropy("correct correct correct"), once);
+        assert_eq!(estimate_entropy("Correct-correct"), once);
+    }
+
+    #[test]
+    fn list_words_with_suffixes_are_weak() {
+        assert!(strength("password123") <= Strength::VeryWeak);
+        assert!(strength("P4ssw0rd!") <= Strength::VeryWeak);
+        assert!(strength("Staple2024") <= Strength::Weak);
+        assert!(strength("correctbattery7") <= Strength::Fair);
+    }
+
+    #[test]
+    fn random_characters_keep_their_entropy() {
+        assert!(strength("hG8kP2xQ9zR4") >= Strength::Strong);
+        assert!(strength("kqzvmtrpwxagbhdlnfcj") >= Strength::Strong);
+        assert!(strength("kqzv-mtrp-wxag-bhdl-nfcj") >= Strength::Strong);
+        assert!(
+            strength("qhwmzvxkcjtbnrgdplfsyaueoiwqzxmvkrtbhgdn") >= Strength::Strong,
+            "random letters that aren't wordlist entries"
+        );
+        assert_eq!(strength(""), Strength::VeryWeak);
+        assert_eq!(strength("aaaaaaaaaaaa"), Strength::VeryWeak);
+    }
+
+    #[test]
+    fn other_scripts_use_their_alphabet() {
+        // Russian for "password".
+        assert!(strength("пароль123") <= Strength::Fair);
+        assert!(strength("пароль123") < strength("пароль-ёжик-пёс-сова-кот"));
+    }
+}
@@ -0,0 +1,113 @@
+//! QR codes for connection links, as shown by every client's "share link"
+//! screen.
+
+use qrcode::render::svg;
+use qrcode::{Color, EcLevel, QrCode};
+
+pub use qrcode::types::QrError;
+
+use crate::links::SimplexLink;
+
+/// Module grid of a QR code, `true` for dark modules, without a quiet zone.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QrMatrix {
+    width: usize,
+    modules: Vec<bool>,
+}
+
+impl QrMatrix {
+    pub fn for_link(link: &SimplexLink) -> Result<Self, QrError> {
+        let code = encode(link)?;
+        let modules = code
+            .to_colors()
+            .into_iter()
+            .map(|c| c == Color::Dark)
+            .collect();
+        Ok(Self {
+            width: code.width(),
+            modules,
+        })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn is_dark(&self, x: usize, y: usize) -> bool {
+        x < self.width && y < self.width && self.modules[y * self.width + x]
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[bool]> {
+        self.modules.chunks(self.width)
+    }
+}
+
+/// Renders `link` as a standalone SVG document at least `min_size` pixels
+/// wide, quiet zone included.
+pub fn svg(link: &SimplexLink, min_size: u32) -> Result<String, QrError> {
+    Ok(encode(link)?
+        .render::<svg::Color>()
+        .min_dimensions(min_size, min_size)
+        .build())
+}
+
+fn encode(link: &SimplexLink) -> Result<QrCode, QrError> {
+    QrCode::with_error_correction_level(link.to_string(), EcLevel::L)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LINK: &str = "https://smp4.simplex.im/a#lJxq0nB3Zl6pX4kVbT2mQ8rYwE5uA1cD9fG7hJ3kL6M";
+
+    fn link(s: &str) -> SimplexLink {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn matrix_for_short_link() {
+        let matrix = QrMatrix::for_link(&link(LINK)).unwrap();
+        // 69 bytes need version 4 at the lowest error correction level.
+        assert_eq!(matrix.width(), 33);
+        assert_eq!(matrix.rows().count(), 33);
+        assert!(matrix.rows().all(|row| row.len() == 33));
+
+        // Finder pattern corners are dark, the separator next to it is light.
+        assert!(matrix.is_dark(0, 0));
+        assert!(matrix.is_dark(32, 0));
+        assert!(matrix.is_dark(0, 32));
+        assert!(!matrix.is_dark(7, 0));
+    }
+
+    #[test]
+    fn is_dark_is_false_out_of_bounds() {
+        let matrix = QrMatrix::for_link(&link(LINK)).unwrap();
+        let width = matrix.width();
+        assert!(!matrix.is_dark(width, 0));
+        assert!(!matrix.is_dark(0, width));
+        assert!(!matrix.is_dark(usize::MAX, usize::MAX));
+    }
+
+    #[test]
+    fn svg_is_at_least_min_size() {
+        let svg = svg(&link(LINK), 300).unwrap();
+        assert!(svg.contains("<svg"), "{svg}");
+        let width: u32 = svg
+            .split_once("width=\"")
+            .and_then(|(_, rest)| rest.split_once('"'))
+            .and_then(|(width, _)| width.parse().ok())
+            .unwrap();
+        assert!(width >= 300, "{width}");
+    }
+
+    #[test]
+    fn overlong_link_is_an_error() {
+        let long = link(&format!(
+            "simplex:/invitation#/?smp=smp&pad={}",
+            "x".repeat(4000)
+        ));
+        assert_eq!(QrMatrix::for_link(&long), Err(QrError::DataTooLong));
+        assert_eq!(svg(&long, 100), Err(QrError::DataTooLong));
+    }
+}
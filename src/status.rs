@@ -0,0 +1,266 @@
+//! Folds chat item status updates into a per-item delivery state.
+
+use std::collections::HashMap;
+use std::sync::mpsc::{channel, Receiver, Sender};
+
+use crate::model::{AChatItem, CIStatus, SndCIStatusProgress};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ItemState {
+    New,
+    Sent,
+    Delivered,
+    Read,
+    Failed(FailureReason),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FailureReason {
+    Auth,
+    /// chatcore's `SndError`, as raw JSON.
+    Error(serde_json::Value),
+    Invalid(String),
+}
+
+impl ItemState {
+    /// Sent items go `New → Sent → Delivered`; received items are `Delivered`
+    /// until read. In groups an item only counts as delivered once every
+    /// member has received it, so a partial `sndRcvd` is still `Sent`.
+    /// Delivery warnings leave a sent item in `Sent`, since
+    /// chatcore keeps retrying. Statuses this crate doesn't know yet map to
+    /// `New`, which never replaces a state an item already has.
+    pub fn from_status(status: &CIStatus) -> Self {
+        match status {
+            CIStatus::SndNew | CIStatus::Unknown(_) => Self::New,
+            CIStatus::SndRcvd {
+                snd_progress: SndCIStatusProgress::Complete,
+                ..
+            }
+            | CIStatus::RcvNew => Self::Delivered,
+            CIStatus::SndSent { .. } | CIStatus::SndRcvd { .. } | CIStatus::SndWarning { .. } => {
+                Self::Sent
+            }
+            CIStatus::RcvRead => Self::Read,
+            CIStatus::SndErrorAuth => Self::Failed(FailureReason::Auth),
+            CIStatus::SndError { agent_error } => {
+                Self::Failed(FailureReason::Error(agent_error.clone()))
+            }
+            CIStatus::Invalid { text } => Self::Failed(FailureReason::Invalid(text.clone())),
+        }
+    }
+
+    pub fn is_final(&self) -> bool {
+        matches!(self, Self::Read | Self::Failed(_))
+    }
+
+    fn rank(&self) -> u8 {
+        match self {
+            Self::New => 0,
+            Self::Sent => 1,
+            Self::Failed(_) => 2,
+            Self::Delivered => 3,
+            Self::Read => 4,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StatusChange {
+    pub item_id: i64,
+    pub from: Option<ItemState>,
+    pub to: ItemState,
+}
+
+/// Tracks the state of chat items by id.
+///
+/// Status updates can arrive out of order, so a state never moves backwards:
+/// an item that is `Delivered` stays so when a late `sndSent` arrives, and a
+/// failure only replaces `New` or `Sent`.
+#[derive(Debug, Default)]
+pub struct CIStatusTracker {
+    items: HashMap<i64, ItemState>,
+    subscribers: Vec<Sender<StatusChange>>,
+}
+
+impl CIStatusTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Applies a status update, returning the change if the state advanced.
+    pub fn update(&mut self, item_id: i64, status: &CIStatus) -> Option<StatusChange> {
+        let next = ItemState::from_status(status);
+        let current = self.items.get(&item_id);
+        let advances = match current {
+            None => true,
+            Some(current) if current.is_final() => false,
+            Some(current) => next.rank() > current.rank(),
+        };
+        if !advances {
+            return None;
+        }
+
+        let change = StatusChange {
+            item_id,
+            from: self.items.insert(item_id, next.clone()),
+            to: next,
+        };
+        self.subscribers
+            .retain(|subscriber| subscriber.send(change.clone()).is_ok());
+        Some(change)
+    }
+
+    /// Applies the status carried by a chat item, as in `newChatItems` and
+    /// `chatItemsStatusesUpdated` events.
+    pub fn update_item(&mut self, item: &AChatItem) -> Option<StatusChange> {
+        let meta = &item.chat_item.meta;
+        self.update(meta.item_id, &meta.item_status)
+    }
+
+    pub fn state(&self, item_id: i64) -> Option<&ItemState> {
+        self.items.get(&item_id)
+    }
+
+    /// Items that are neither delivered nor failed yet.
+    pub fn pending(&self) -> impl Iterator<Item = (i64, &ItemState)> {
+        self.items
+            .iter()
+            .filter(|(_, state)| matches!(state, ItemState::New | ItemState::Sent))
+            .map(|(id, state)| (*id, state))
+    }
+
+    pub fn failed(&self) -> impl Iterator<Item = (i64, &FailureReason)> {
+        self.items.iter().filter_map(|(id, state)| match state {
+            ItemState::Failed(reason) => Some((*id, reason)),
+            _ => None,
+        })
+    }
+
+    /// Stops tracking an item, e.g. once it was deleted.
+    pub fn forget(&mut self, item_id: i64) -> Option<ItemState> {
+        self.items.remove(&item_id)
+    }
+
+    /// Returns a receiver that gets every subsequent state change.
+    pub fn subscribe(&mut self) -> Receiver<StatusChange> {
+        let (sender, receiver) = channel();
+        self.subscribers.push(sender);
+        receiver
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::MsgReceiptStatus;
+
+    fn sent() -> CIStatus {
+        CIStatus::SndSent {
+            snd_progress: SndCIStatusProgress::Complete,
+        }
+    }
+
+    fn rcvd(snd_progress: SndCIStatusProgress) -> CIStatus {
+        CIStatus::SndRcvd {
+            msg_rcpt_status: MsgReceiptStatus::Ok,
+            snd_progress,
+        }
+    }
+
+    #[test]
+    fn partial_receipt_is_sent() {
+        assert_eq!(
+            ItemState::from_status(&rcvd(SndCIStatusProgress::Partial)),
+            ItemState::Sent
+        );
+        assert_eq!(
+            ItemState::from_status(&rcvd(SndCIStatusProgress::Complete)),
+            ItemState::Delivered
+        );
+
+        let mut tracker = CIStatusTracker::new();
+        tracker.update(1, &sent());
+        assert_eq!(tracker.update(1, &rcvd(SndCIStatusProgress::Partial)), None);
+        assert_eq!(tracker.pending().count(), 1);
+        let change = tracker.update(1, &rcvd(SndCIStatusProgress::Complete));
+        assert_eq!(change.map(|c| c.to), Some(ItemState::Delivered));
+    }
+
+    #[test]
+    fn out_of_order_updates_dont_regress() {
+        let mut tracker = CIStatusTracker::new();
+        tracker.update(1, &rcvd(SndCIStatusProgress::Complete));
+        assert_eq!(tracker.update(1, &sent()), None);
+        assert_eq!(tracker.update(1, &CIStatus::SndNew), None);
+        assert_eq!(
+            tracker.update(
+                1,
+                &CIStatus::Unknown(serde_json::json!({"type": "sndPending"}))
+            ),
+            None
+        );
+        assert_eq!(tracker.state(1), Some(&ItemState::Delivered));
+
+        // A failure only replaces `New` or `Sent`.
+        assert_eq!(tracker.update(1, &CIStatus::SndErrorAuth), None);
+        tracker.update(2, &sent());
+        let change = tracker.update(2, &CIStatus::SndErrorAuth).unwrap();
+        assert_eq!(change.from, Some(ItemState::Sent));
+        assert_eq!(change.to, ItemState::Failed(FailureReason::Auth));
+    }
+
+    #[test]
+    fn final_states_are_kept() {
+        let mut tracker = CIStatusTracker::new();
+        tracker.update(1, &CIStatus::RcvRead);
+        assert_eq!(tracker.update(1, &CIStatus::RcvNew), None);
+        assert_eq!(tracker.state(1), Some(&ItemState::Read));
+
+        tracker.update(2, &CIStatus::SndErrorAuth);
+        assert_eq!(
+            tracker.update(2, &rcvd(SndCIStatusProgress::Complete)),
+            None
+        );
+        assert_eq!(tracker.update(2, &CIStatus::RcvRead), None);
+        assert_eq!(
+            tracker.failed().collect::<Vec<_>>(),
+            [(2, &FailureReason::Auth)]
+        );
+
+        assert_eq!(
+            tracker.forget(2),
+            Some(ItemState::Failed(FailureReason::Auth))
+        );
+        assert!(tracker.update(2, &sent()).is_some());
+    }
+
+    #[test]
+    fn subscribers_get_changes() {
+        let mut tracker = CIStatusTracker::new();
+        let receiver = tracker.subscribe();
+        let dropped = tracker.subscribe();
+        drop(dropped);
+
+        tracker.update(1, &CIStatus::SndNew);
+        tracker.update(1, &sent());
+        tracker.update(1, &CIStatus::SndNew);
+        assert_eq!(tracker.subscribers.len(), 1);
+
+        let changes: Vec<StatusChange> = receiver.try_iter().collect();
+        assert_eq!(
+            changes,
+            [
+                StatusChange {
+                    item_id: 1,
+                    from: None,
+                    to: ItemState::New,
+                },
+                StatusChange {
+                    item_id: 1,
+                    from: Some(ItemState::New),
+                    to: ItemState::Sent,
+                },
+            ]
+        );
+    }
+}
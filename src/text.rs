@@ -0,0 +1,35 @@
+//! Pure-Rust versions of chatcore's text utilities.
+
+/// Length that chatcore's `chat_json_length` reports for `s`: the number of
+/// bytes in its JSON string encoding, without the surrounding quotes. This is
+/// what chatcore checks message size limits against.
+pub fn json_length(s: &str) -> usize {
+    s.chars()
+        .map(|c| match c {
+            '"' | '\\' | '\n' | '\r' | '\t' => 2,
+            c if c < ' ' => 6,
+            c => c.len_utf8(),
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::json_length;
+
+    #[test]
+    fn counts_utf8_bytes() {
+        assert_eq!(json_length(""), 0);
+        assert_eq!(json_length("hello"), 5);
+        assert_eq!(json_length("é"), 2);
+        assert_eq!(json_length("👋"), 4);
+    }
+
+    #[test]
+    fn counts_escapes() {
+        assert_eq!(json_length("a\"b"), 4);
+        assert_eq!(json_length("a\\b"), 4);
+        assert_eq!(json_length("line\nbreak\ttab\r"), 18);
+        assert_eq!(json_length("\u{1}"), 6);
+    }
+}
@@ -0,0 +1,139 @@
+//! Image previews in the form SimpleX clients put into image messages: a
+//! downscaled JPEG as a base64 `data:` URI small enough to be sent inline.
+
+use std::io::Cursor;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use image::codecs::jpeg::JpegEncoder;
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageDecoder, ImageError, ImageReader, ImageResult, RgbImage};
+
+/// Size limit the reference clients use for image previews.
+pub const MAX_PREVIEW_SIZE: usize = 14_000;
+
+/// Longest side of the image before the first encoding attempt. Previews are
+/// a few hundred pixels at most, so there is no point in JPEG-encoding the
+/// full-resolution original.
+const MAX_PREVIEW_DIMENSION: u32 = 800;
+
+const DATA_URI_PREFIX: &str = "data:image/jpg;base64,";
+const JPEG_QUALITY: u8 = 85;
+
+/// Builds a preview of the encoded image in `bytes` that fits into
+/// [`MAX_PREVIEW_SIZE`].
+pub fn thumbnail(bytes: &[u8]) -> ImageResult<String> {
+    thumbnail_with_limit(bytes, MAX_PREVIEW_SIZE)
+}
+
+/// Builds a preview whose data URI is at most `max_len` bytes long, shrinking
+/// the image until it fits. EXIF orientation is applied and transparent
+/// pixels are composited onto white, as JPEG has no alpha channel.
+pub fn thumbnail_with_limit(bytes: &[u8], max_len: usize) -> ImageResult<String> {
+    let mut decoder = ImageReader::new(Cursor::new(bytes))
+        .with_guessed_format()?
+        .into_decoder()?;
+    let orientation = decoder.orientation()?;
+    let mut image = DynamicImage::from_decoder(decoder)?;
+    image.apply_orientation(orientation);
+
+    if image.width().max(image.height()) > MAX_PREVIEW_DIMENSION {
+        image = image.resize(
+            MAX_PREVIEW_DIMENSION,
+            MAX_PREVIEW_DIMENSION,
+            FilterType::Triangle,
+        );
+    }
+    let mut preview = DynamicImage::ImageRgb8(on_white(&image));
+
+    loop {
+        let uri = data_uri(&preview)?;
+        if uri.len() <= max_len {
+            return Ok(uri);
+        }
+
+        let scale = (max_len as f64 / uri.len() as f64).sqrt().min(0.9);
+        let width = (preview.width() as f64 * scale) as u32;
+        let height = (preview.height() as f64 * scale) as u32;
+        if width == 0 || height == 0 {
+            return Err(ImageError::Limits(image::error::LimitError::from_kind(
+                image::error::LimitErrorKind::DimensionError,
+            )));
+        }
+        preview = preview.resize_exact(width, height, FilterType::Triangle);
+    }
+}
+
+fn on_white(image: &DynamicImage) -> RgbImage {
+    let rgba = image.to_rgba8();
+    RgbImage::from_fn(rgba.width(), rgba.height(), |x, y| {
+        let [r, g, b, a] = rgba.get_pixel(x, y).0;
+        let blend = |c: u8| ((c as u32 * a as u32 + 255 * (255 - a as u32) + 127) / 255) as u8;
+        image::Rgb([blend(r), blend(g), blend(b)])
+    })
+}
+
+fn data_uri(image: &DynamicImage) -> ImageResult<String> {
+    let mut jpeg = Vec::new();
+    image.write_with_encoder(JpegEncoder::new_with_quality(&mut jpeg, JPEG_QUALITY))?;
+    Ok(format!("{DATA_URI_PREFIX}{}", STANDARD.encode(jpeg)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{ImageEncoder, ImageFormat, Rgba, RgbaImage};
+
+    fn png(image: RgbaImage) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        DynamicImage::ImageRgba8(image)
+            .write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)
+            .unwrap();
+        bytes
+    }
+
+    fn decode(uri: &str) -> DynamicImage {
+        let jpeg = STANDARD.decode(&uri[DATA_URI_PREFIX.len()..]).unwrap();
+        image::load_from_memory(&jpeg).unwrap()
+    }
+
+    #[test]
+    fn large_image_fits_preview_size() {
+        let noisy = RgbaImage::from_fn(3000, 2000, |x, y| {
+            let v = x.wrapping_mul(7919) ^ y.wrapping_mul(104_729);
+            Rgba([v as u8, (v >> 8) as u8, (x ^ y) as u8, 255])
+        });
+        let uri = thumbnail(&png(noisy)).unwrap();
+        assert!(uri.starts_with(DATA_URI_PREFIX));
+        assert!(uri.len() <= MAX_PREVIEW_SIZE, "{} bytes", uri.len());
+
+        let preview = decode(&uri);
+        assert!(preview.width() <= MAX_PREVIEW_DIMENSION);
+        assert!(preview.width() > preview.height());
+    }
+
+    #[test]
+    fn exif_orientation_is_applied() {
+        // Big-endian TIFF header with one IFD entry: Orientation = 6, i.e.
+        // the stored pixels must be rotated 90° clockwise for display.
+        let exif = [
+            b'M', b'M', 0, 42, 0, 0, 0, 8, 0, 1, 0x01, 0x12, 0, 3, 0, 0, 0, 1, 0, 6, 0, 0, 0, 0, 0,
+            0,
+        ];
+        let landscape = RgbImage::from_pixel(40, 20, image::Rgb([200, 50, 50]));
+        let mut jpeg = Vec::new();
+        let mut encoder = JpegEncoder::new(&mut jpeg);
+        encoder.set_exif_metadata(exif.to_vec()).unwrap();
+        landscape.write_with_encoder(encoder).unwrap();
+
+        let preview = decode(&thumbnail(&jpeg).unwrap());
+        assert_eq!((preview.width(), preview.height()), (20, 40));
+    }
+
+    #[test]
+    fn transparency_becomes_white() {
+        let clear = RgbaImage::from_pixel(16, 16, Rgba([0, 0, 0, 0]));
+        let preview = decode(&thumbnail(&png(clear)).unwrap()).to_rgb8();
+        assert!(preview.pixels().all(|p| p.0.iter().all(|&c| c > 245)));
+    }
+}